@@ -0,0 +1,237 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    str::from_utf8,
+};
+
+use anyhow::{anyhow, Result};
+
+/// Abstracts the VCS operations `tree-hoprs` needs over a worktree/workspace,
+/// so repositories can opt into a backend other than git (e.g. Jujutsu).
+pub trait Backend {
+    /// Create (or switch to) a worktree for `branch` at `path`, based off `base`.
+    fn create_worktree(&self, base: &Path, path: &Path, branch: &str, dry_run: bool) -> Result<()>;
+    /// List the known worktrees/workspaces as `(path, branch)` pairs.
+    fn list_worktrees(&self, base: &Path) -> Result<Vec<(PathBuf, String)>>;
+    /// Remove the worktree/workspace at `path`.
+    fn remove_worktree(&self, base: &Path, path: &Path, dry_run: bool) -> Result<()>;
+    /// Delete `branch` after its worktree/workspace has been removed.
+    fn delete_branch(&self, base: &Path, branch: &str, dry_run: bool) -> Result<()>;
+    /// Bring the base tree up to date with its upstream.
+    fn update_base(&self, base: &Path, dry_run: bool) -> Result<()>;
+}
+
+/// Returns the `Backend` for a repository's configured `vcs` value.
+/// Unrecognized values fall back to `GitBackend`, matching git being the default.
+pub fn backend_for(vcs: &str) -> Box<dyn Backend> {
+    match vcs {
+        "jj" => Box::new(JjBackend),
+        _ => Box::new(GitBackend),
+    }
+}
+
+/// Today's behavior: drives worktrees through the `git` CLI.
+pub struct GitBackend;
+
+impl Backend for GitBackend {
+    fn create_worktree(&self, base: &Path, path: &Path, branch: &str, dry_run: bool) -> Result<()> {
+        let mut branch_cmd = Command::new("git");
+        branch_cmd.arg("branch").arg(branch).current_dir(base);
+        // XXX: Should fail if branch already exists
+        if dry_run {
+            println!("Would create branch {}", branch);
+            println!("Would run command {:?}", branch_cmd);
+        } else {
+            branch_cmd.status()?;
+        }
+
+        let mut worktree_cmd = Command::new("git");
+        worktree_cmd.current_dir(base);
+        if let Ok(existing) = std::fs::read_dir(path) {
+            if existing.count() > 0 {
+                println!(
+                    "Worktree {} already exists, switching branch",
+                    path.display()
+                );
+                worktree_cmd.current_dir(path);
+                worktree_cmd.arg("switch").arg(branch);
+            } else {
+                worktree_cmd
+                    .arg("worktree")
+                    .arg("add")
+                    .arg(path)
+                    .arg(branch);
+            }
+        } else {
+            worktree_cmd
+                .arg("worktree")
+                .arg("add")
+                .arg(path)
+                .arg(branch);
+        }
+
+        if dry_run {
+            println!("Would create worktree {}", path.display());
+            println!("Would run command {:?}", worktree_cmd);
+        } else {
+            let status = worktree_cmd.status()?;
+            if !status.success() {
+                return Err(anyhow!("{:?} failed with {}", worktree_cmd, status));
+            }
+        }
+        Ok(())
+    }
+
+    fn list_worktrees(&self, base: &Path) -> Result<Vec<(PathBuf, String)>> {
+        let mut cmd = Command::new("git");
+        cmd.arg("worktree").arg("list").current_dir(base);
+        let output = cmd.output()?;
+
+        let mut worktrees = Vec::new();
+        for line in from_utf8(&output.stdout)?.lines() {
+            let items: Vec<&str> = line.split_whitespace().collect();
+            if items.len() < 3 {
+                continue;
+            }
+            let branch = items[2]
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string();
+            worktrees.push((PathBuf::from(items[0]), branch));
+        }
+        Ok(worktrees)
+    }
+
+    fn remove_worktree(&self, base: &Path, path: &Path, dry_run: bool) -> Result<()> {
+        let mut cmd = Command::new("git");
+        cmd.arg("worktree")
+            .arg("remove")
+            .arg(path)
+            .current_dir(base);
+        if dry_run {
+            println!("Would run command {:?}", cmd);
+        } else {
+            let status = cmd.status()?;
+            if !status.success() {
+                return Err(anyhow!(
+                    "`git worktree remove {}` failed with {}",
+                    path.display(),
+                    status
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_branch(&self, base: &Path, branch: &str, dry_run: bool) -> Result<()> {
+        let mut cmd = Command::new("git");
+        cmd.arg("branch").arg("-d").arg(branch).current_dir(base);
+        if dry_run {
+            println!("Would run command {:?}", cmd);
+        } else {
+            let status = cmd.status()?;
+            if !status.success() {
+                return Err(anyhow!("`git branch -d {}` failed with {}", branch, status));
+            }
+        }
+        Ok(())
+    }
+
+    fn update_base(&self, base: &Path, dry_run: bool) -> Result<()> {
+        let mut cmd = Command::new("git");
+        cmd.arg("pull").current_dir(base);
+        if dry_run {
+            println!("Would run command {:?}", cmd);
+        } else {
+            cmd.status()?;
+        }
+        Ok(())
+    }
+}
+
+/// Drives the same worktree/archive workflow through Jujutsu workspaces.
+pub struct JjBackend;
+
+impl Backend for JjBackend {
+    fn create_worktree(
+        &self,
+        base: &Path,
+        path: &Path,
+        _branch: &str,
+        dry_run: bool,
+    ) -> Result<()> {
+        // Deliberately no `--name`: jj derives the workspace name from the
+        // last component of `path`, which is what `list_worktrees` below
+        // assumes when it reconstructs a path from `jj workspace list`.
+        let mut cmd = Command::new("jj");
+        cmd.arg("workspace").arg("add").arg(path).current_dir(base);
+        if dry_run {
+            println!("Would create workspace {}", path.display());
+            println!("Would run command {:?}", cmd);
+        } else {
+            cmd.status()?;
+        }
+        Ok(())
+    }
+
+    fn list_worktrees(&self, base: &Path) -> Result<Vec<(PathBuf, String)>> {
+        let mut cmd = Command::new("jj");
+        cmd.arg("workspace").arg("list").current_dir(base);
+        let output = cmd.output()?;
+
+        // NOTE: `jj workspace list` reports workspace names, not paths. We
+        // assume the common convention that `jj workspace add <path>` left the
+        // workspace named after the path's last component.
+        let mut worktrees = Vec::new();
+        for line in from_utf8(&output.stdout)?.lines() {
+            let Some((name, _)) = line.split_once(':') else {
+                continue;
+            };
+            let name = name.trim();
+            worktrees.push((base.parent().unwrap_or(base).join(name), name.to_string()));
+        }
+        Ok(worktrees)
+    }
+
+    fn remove_worktree(&self, base: &Path, path: &Path, dry_run: bool) -> Result<()> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut cmd = Command::new("jj");
+        cmd.arg("workspace")
+            .arg("forget")
+            .arg(&name)
+            .current_dir(base);
+        if dry_run {
+            println!("Would run command {:?}", cmd);
+        } else {
+            let status = cmd.status()?;
+            if !status.success() {
+                return Err(anyhow!(
+                    "`jj workspace forget {}` failed with {}",
+                    name,
+                    status
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_branch(&self, _base: &Path, _branch: &str, _dry_run: bool) -> Result<()> {
+        // `jj workspace forget` already disposes of the workspace; jj has no
+        // separate branch-delete step analogous to `git branch -d`.
+        Ok(())
+    }
+
+    fn update_base(&self, base: &Path, dry_run: bool) -> Result<()> {
+        let mut cmd = Command::new("jj");
+        cmd.arg("git").arg("fetch").current_dir(base);
+        if dry_run {
+            println!("Would run command {:?}", cmd);
+        } else {
+            cmd.status()?;
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,11 @@
-use std::{collections::HashMap, env::var, fs, io::BufRead, process::Command, str::from_utf8};
+use std::{
+    collections::HashMap,
+    env::{args, var},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    str::from_utf8,
+};
 
 use anyhow::{anyhow, Result};
 use clap::{Parser, Subcommand};
@@ -6,6 +13,8 @@ use comfy_table::Table;
 use dialoguer::Input;
 use serde::{Deserialize, Serialize};
 
+mod backend;
+
 /// The config file path
 /// Defaults to `~/.config/tree-hoprs.json`
 #[allow(non_snake_case)]
@@ -74,6 +83,31 @@ enum TreeCommand {
     DeleteRepo {
         repo_name: String,
     },
+    /// Run a command across all active worktrees
+    Foreach {
+        /// Only run in worktrees whose branch matches this glob
+        #[arg(short = 'B', long = "branch")]
+        branch: Option<String>,
+        /// The command (and its arguments) to run in each worktree
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Physically remove archived worktrees (or all of them if no branch is given)
+    Prune {
+        branch_name: Option<String>,
+    },
+    /// Reactivate an archived worktree
+    Restore {
+        branch_name: String,
+    },
+}
+
+fn default_vcs() -> String {
+    "git".to_string()
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -81,6 +115,25 @@ struct RepoConfig {
     base_tree: String,
     base_path: String,
     inactive_trees: Vec<String>,
+    /// Which VCS backend drives this repository's worktrees, e.g. "git" or "jj"
+    #[serde(default = "default_vcs")]
+    vcs: String,
+    /// Whether to run `git submodule update --init --recursive` after creating a worktree
+    #[serde(default = "default_true")]
+    init_submodules: bool,
+    /// Lifecycle commands run after a worktree is created or before it is archived
+    #[serde(default)]
+    hooks: Hooks,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct Hooks {
+    /// Commands run, in order, in the new worktree after it is created
+    #[serde(default)]
+    post_create: Vec<String>,
+    /// Commands run, in order, in the worktree before it is archived
+    #[serde(default)]
+    post_remove: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -89,10 +142,74 @@ struct Config {
     repo: HashMap<String, RepoConfig>,
     #[serde(rename = "active_repository")]
     active_repo: String,
+    /// Shorthand command expansions, e.g. `"co": ["list", "--raw"]`
+    #[serde(default)]
+    aliases: HashMap<String, Vec<String>>,
+}
+
+/// Expands a user-defined alias in the first positional token of `raw_args`,
+/// mirroring cargo's alias resolution. Expansion only ever happens once, so
+/// an alias expanding to something starting with its own name (e.g. `"list":
+/// ["list", "--raw"]`, extending a builtin) is not a cycle and is allowed.
+///
+/// This does not raise a dedicated "alias cycle" error: a purely
+/// self-referencing alias with no builtin behind it (e.g. `"co": ["co"]`)
+/// is left unexpanded and only fails later, as clap's generic "unrecognized
+/// subcommand" error. The one-shot design makes an infinite expansion loop
+/// impossible, so there's nothing further to guard against.
+fn expand_aliases(raw_args: Vec<String>) -> Result<Vec<String>> {
+    let aliases = get_config_file().map(|c| c.aliases).unwrap_or_default();
+    Ok(expand_aliases_with(&aliases, raw_args))
+}
+
+/// Pure alias-expansion core of [`expand_aliases`], split out so it can be
+/// exercised without a config file on disk.
+fn expand_aliases_with(
+    aliases: &HashMap<String, Vec<String>>,
+    raw_args: Vec<String>,
+) -> Vec<String> {
+    // Global flags that consume the following token, so it isn't mistaken
+    // for the subcommand.
+    let value_flags = ["-b", "--base", "-p", "--path", "-r", "--repo"];
+
+    let mut result = Vec::with_capacity(raw_args.len());
+    let mut iter = raw_args.into_iter();
+    if let Some(bin) = iter.next() {
+        result.push(bin);
+    }
+
+    let mut expect_value = false;
+    let mut expanded = false;
+    for arg in iter {
+        if expect_value {
+            result.push(arg);
+            expect_value = false;
+            continue;
+        }
+        if arg.starts_with('-') {
+            expect_value = value_flags.contains(&arg.as_str());
+            result.push(arg);
+            continue;
+        }
+        if !expanded {
+            expanded = true;
+            if let Some(expansion) = aliases.get(&arg) {
+                // The expansion's tokens are never re-scanned for further
+                // aliases (we only expand once), so an alias that extends a
+                // builtin with its own name, e.g. `"list": ["list", "--raw"]`,
+                // is not a cycle. A cycle would require re-expanding the
+                // expansion itself, which this function never does.
+                result.extend(expansion.clone());
+                continue;
+            }
+        }
+        result.push(arg);
+    }
+    result
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let args = Args::parse_from(expand_aliases(args().collect())?);
     if args.verbose {
         dbg!(&args);
     }
@@ -102,6 +219,9 @@ fn main() -> Result<()> {
         base_tree: args.base_branch.unwrap_or(String::new()),
         base_path: args.base_path.unwrap_or(String::new()),
         inactive_trees: Vec::new(),
+        vcs: default_vcs(),
+        init_submodules: default_true(),
+        hooks: Hooks::default(),
     };
 
     // Check if optional values are passed
@@ -153,6 +273,18 @@ fn main() -> Result<()> {
             delete_repo(repo_name)
         }
         TreeCommand::GetRepos => get_repos(),
+        TreeCommand::Foreach {
+            branch,
+            args: cmd_args,
+        } => foreach_worktree(values, cmd_args, branch, args.dry_run),
+        TreeCommand::Prune { branch_name } => {
+            println!("Pruning archived worktree(s)");
+            prune_worktrees(values, branch_name, args.dry_run)
+        }
+        TreeCommand::Restore { branch_name } => {
+            println!("Restoring worktree {}", branch_name);
+            restore_worktree(values, branch_name, args.dry_run)
+        }
     }
 }
 
@@ -188,6 +320,9 @@ fn add_repo(repo_name: String, base_tree: String, base_path: String) -> Result<(
             base_tree,
             base_path,
             inactive_trees: Vec::new(),
+            vcs: default_vcs(),
+            init_submodules: default_true(),
+            hooks: Hooks::default(),
         },
     );
     fs::write(CONFIG_FILE(), serde_json::to_string_pretty(&config)?)?;
@@ -225,6 +360,7 @@ fn create_config_file(values: &mut RepoConfig, repo: &Option<String>) -> Result<
     let mut config = Config {
         repo: HashMap::new(),
         active_repo: repo_name.clone(),
+        aliases: HashMap::new(),
     };
     values.base_tree = base_tree;
     values.base_path = base_path;
@@ -244,26 +380,64 @@ fn get_values_from_config_file(repo: &Option<String>) -> Result<RepoConfig> {
     }
 }
 
-fn create_worktree(mut values: RepoConfig, branch_name: String, dry_run: bool) -> Result<()> {
-    let mut pull_cmd = Command::new("git");
-    pull_cmd
-        .current_dir(format!("{}/{}", values.base_path, values.base_tree))
-        .arg("pull");
-    pull_cmd.status()?;
-
-    // Create branch if it doesn't exist
-    let mut branch_cmd = Command::new("git");
-    branch_cmd
-        .arg("branch")
-        .arg(&branch_name)
-        .current_dir(format!("{}/{}", values.base_path, values.base_tree));
-    // XXX: Should fail if branch already exists
+/// Initializes and updates submodules in `worktree_path`, skipping cleanly
+/// when `base` has no `.gitmodules`.
+fn update_submodules(base: &Path, worktree_path: &Path, dry_run: bool) -> Result<()> {
+    if !base.join(".gitmodules").exists() {
+        return Ok(());
+    }
+
+    let mut cmd = Command::new("git");
+    cmd.arg("submodule")
+        .arg("update")
+        .arg("--init")
+        .arg("--recursive")
+        .current_dir(worktree_path);
     if dry_run {
-        println!("Would create branch {}", branch_name);
-        println!("Would run command {:?}", branch_cmd);
+        println!("Would run command {:?}", cmd);
     } else {
-        branch_cmd.status()?;
-    };
+        cmd.status()?;
+    }
+    Ok(())
+}
+
+/// Runs lifecycle hook commands in order inside `worktree_path`, exporting
+/// `TREEHOPRS_WORKTREE_PATH`, `TREEHOPRS_BRANCH` and `TREEHOPRS_BASE_PATH`.
+/// Aborts the remaining hooks on the first non-zero exit.
+fn run_hooks(
+    commands: &[String],
+    worktree_path: &Path,
+    branch_name: &str,
+    base_path: &Path,
+    dry_run: bool,
+) -> Result<()> {
+    for command in commands {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(command)
+            .current_dir(worktree_path)
+            .env("TREEHOPRS_WORKTREE_PATH", worktree_path)
+            .env("TREEHOPRS_BRANCH", branch_name)
+            .env("TREEHOPRS_BASE_PATH", base_path);
+
+        if dry_run {
+            println!("Would run hook {:?}", cmd);
+            continue;
+        }
+
+        let status = cmd.status()?;
+        if !status.success() {
+            return Err(anyhow!("Hook `{}` failed with {}", command, status));
+        }
+    }
+    Ok(())
+}
+
+fn create_worktree(mut values: RepoConfig, branch_name: String, dry_run: bool) -> Result<()> {
+    let backend = backend::backend_for(&values.vcs);
+    let base_path = PathBuf::from(format!("{}/{}", values.base_path, values.base_tree));
+
+    backend.update_base(&base_path, dry_run)?;
 
     // Create worktree
     let worktree_path;
@@ -279,42 +453,40 @@ fn create_worktree(mut values: RepoConfig, branch_name: String, dry_run: bool) -
         worktree_path = format!("{}/{}", values.base_path, worktree_name);
     }
 
-    // Check if worktree already exists
-    let mut worktree_cmd = Command::new("git");
-    worktree_cmd.current_dir(format!("{}/{}", values.base_path, values.base_tree));
-
-    if let Ok(worktree) = fs::read_dir(&worktree_path) {
-        if worktree.count() > 0 {
-            println!(
-                "Worktree {} already exists, switching branch",
-                worktree_path
-            );
-            // Switch the branch in the existing worktree
-            worktree_cmd.current_dir(&worktree_path);
-            worktree_cmd.arg("switch").arg(&branch_name);
-        }
-    } else {
-        worktree_cmd
-            .arg("worktree")
-            .arg("add")
-            .arg(&worktree_path)
-            .arg(&branch_name);
-    }
-    if dry_run {
-        println!("Would create worktree {}", &worktree_path);
-        println!("Would run command {:?}", worktree_cmd);
-    } else {
-        worktree_cmd.status()?;
-
+    backend.create_worktree(
+        &base_path,
+        &PathBuf::from(&worktree_path),
+        &branch_name,
+        dry_run,
+    )?;
+
+    // Commit the reused-slot bookkeeping before running anything (submodules,
+    // hooks) that could still fail — the worktree already exists on disk at
+    // this point, so it must not be left mismarked as inactive.
+    if !dry_run {
         // NOTE: There is a better way to do this. Could use pop or something :/
         if values.inactive_trees.contains(&worktree_path) {
             values.inactive_trees.remove(0);
             let mut config: Config = serde_json::from_str(&fs::read_to_string(CONFIG_FILE())?)?;
-            config.repo.insert(config.active_repo.clone(), values);
+            config
+                .repo
+                .insert(config.active_repo.clone(), values.clone());
             fs::write(CONFIG_FILE(), serde_json::to_string_pretty(&config)?)?;
         }
     };
 
+    if values.init_submodules {
+        update_submodules(&base_path, Path::new(&worktree_path), dry_run)?;
+    }
+
+    run_hooks(
+        &values.hooks.post_create,
+        Path::new(&worktree_path),
+        &branch_name,
+        &base_path,
+        dry_run,
+    )?;
+
     println!(
         "Branch {} created in worktree {}",
         &branch_name, worktree_path
@@ -322,31 +494,86 @@ fn create_worktree(mut values: RepoConfig, branch_name: String, dry_run: bool) -
     Ok(())
 }
 
+/// Reports whether `path` has uncommitted changes and how far it has
+/// diverged from its upstream, tolerating the no-upstream case.
+fn worktree_status(path: &Path) -> (String, String, String) {
+    let dirty_count = Command::new("git")
+        .arg("status")
+        .arg("--porcelain")
+        .current_dir(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            from_utf8(&output.stdout)
+                .unwrap_or_default()
+                .lines()
+                .count()
+        });
+
+    let status = match dirty_count {
+        Some(0) => "clean".to_string(),
+        Some(n) => format!("dirty ({})", n),
+        None => "unknown".to_string(),
+    };
+
+    let divergence = Command::new("git")
+        .arg("rev-list")
+        .arg("--left-right")
+        .arg("--count")
+        .arg("@{u}...HEAD")
+        .current_dir(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success());
+
+    let (ahead, behind) = match divergence {
+        Some(output) => {
+            let counts: Vec<String> = from_utf8(&output.stdout)
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect();
+            match counts.as_slice() {
+                [behind, ahead] => (ahead.clone(), behind.clone()),
+                _ => ("-".to_string(), "-".to_string()),
+            }
+        }
+        None => ("-".to_string(), "-".to_string()),
+    };
+
+    (status, ahead, behind)
+}
+
 fn list_worktrees(values: RepoConfig, raw: bool) -> Result<()> {
-    let mut cmd = Command::new("git");
-    cmd.arg("worktree")
-        .arg("list")
-        .current_dir(format!("{}/{}", values.base_path, values.base_tree));
-    let output = cmd.output()?;
+    let backend = backend::backend_for(&values.vcs);
+    let base_path = PathBuf::from(format!("{}/{}", values.base_path, values.base_tree));
+    let worktrees = backend.list_worktrees(&base_path)?;
 
     if raw {
-        for line in output.stdout.lines() {
-            let items: Vec<&str> = line.as_ref().unwrap().split_whitespace().collect();
-            if values.inactive_trees.contains(&items[0].to_string()) {
+        for (path, branch) in &worktrees {
+            if values.inactive_trees.contains(&path.display().to_string()) {
                 continue;
             }
-            println!("{}", &items[2][1..items[2].len() - 1]);
+            let (status, ahead, behind) = worktree_status(path);
+            println!("{}\t{}\t{}\t{}", branch, status, ahead, behind);
         }
     } else {
         let mut table = Table::new();
-        table.set_header(["Path", "Branch"]);
+        table.set_header(["Path", "Branch", "Status", "↑ahead", "↓behind"]);
 
-        for line in output.stdout.lines() {
-            let items: Vec<&str> = line.as_ref().unwrap().split_whitespace().collect();
-            if values.inactive_trees.contains(&items[0].to_string()) {
+        for (path, branch) in &worktrees {
+            if values.inactive_trees.contains(&path.display().to_string()) {
                 continue;
             }
-            table.add_row([items[0], items[2]]);
+            let (status, ahead, behind) = worktree_status(path);
+            table.add_row([
+                path.display().to_string(),
+                branch.clone(),
+                status,
+                ahead,
+                behind,
+            ]);
         }
         println!("{}", table);
     }
@@ -354,21 +581,17 @@ fn list_worktrees(values: RepoConfig, raw: bool) -> Result<()> {
 }
 
 fn delete_worktree(mut values: RepoConfig, branch_name: String, dry_run: bool) -> Result<()> {
-    let mut worktree_cmd = Command::new("git");
-    worktree_cmd
-        .arg("worktree")
-        .arg("list")
-        .current_dir(format!("{}/{}", values.base_path, values.base_tree));
-    let output = worktree_cmd.output()?;
-    let result = from_utf8(&output.stdout)?
-        .lines()
-        .find(|&line| line.to_string().contains(&branch_name))
-        .map(|line| line.to_string());
+    let backend = backend::backend_for(&values.vcs);
+    let base_path = PathBuf::from(format!("{}/{}", values.base_path, values.base_tree));
+    let worktrees = backend.list_worktrees(&base_path)?;
+    let result = worktrees.iter().find(|(path, branch)| {
+        branch == &branch_name || path.display().to_string().contains(&branch_name)
+    });
     if result.is_none() {
         println!("Worktree {} does not exist", branch_name);
         return Ok(());
     }
-    let worktree_path = result.unwrap().split_whitespace().collect::<Vec<&str>>()[0].to_string();
+    let worktree_path = result.unwrap().0.display().to_string();
     let mut config: Config = serde_json::from_str(&fs::read_to_string(CONFIG_FILE())?)?;
     if config
         .repo
@@ -383,6 +606,17 @@ fn delete_worktree(mut values: RepoConfig, branch_name: String, dry_run: bool) -
 
     if dry_run {
         println!("Would archive worktree {}", &worktree_path);
+    }
+
+    run_hooks(
+        &values.hooks.post_remove,
+        Path::new(&worktree_path),
+        &branch_name,
+        &base_path,
+        dry_run,
+    )?;
+
+    if dry_run {
         return Ok(());
     }
 
@@ -394,15 +628,272 @@ fn delete_worktree(mut values: RepoConfig, branch_name: String, dry_run: bool) -
     Ok(())
 }
 
-fn update_main_worktree(values: RepoConfig, dry_run: bool) -> Result<()> {
-    let mut cmd = Command::new("git");
-    cmd.arg("pull")
-        .current_dir(format!("{}/{}", values.base_path, values.base_tree));
-    if dry_run {
-        println!("Would run command {:?}", cmd);
-    } else {
-        cmd.status()?;
+fn prune_worktrees(
+    mut values: RepoConfig,
+    branch_name: Option<String>,
+    dry_run: bool,
+) -> Result<()> {
+    let backend = backend::backend_for(&values.vcs);
+    let base_path = PathBuf::from(format!("{}/{}", values.base_path, values.base_tree));
+    let worktrees = backend.list_worktrees(&base_path)?;
+
+    let targets: Vec<String> = match &branch_name {
+        Some(branch) => {
+            let path = worktrees
+                .iter()
+                .find(|(_, b)| b == branch)
+                .map(|(path, _)| path.display().to_string())
+                .ok_or_else(|| anyhow!("Worktree {} does not exist", branch))?;
+            if !values.inactive_trees.contains(&path) {
+                return Err(anyhow!("Worktree {} is not archived", branch));
+            }
+            vec![path]
+        }
+        None => values.inactive_trees.clone(),
     };
 
+    if targets.is_empty() {
+        println!("No archived worktrees to prune");
+        return Ok(());
+    }
+
+    for target in &targets {
+        let branch = worktrees
+            .iter()
+            .find(|(path, _)| &path.display().to_string() == target);
+
+        backend.remove_worktree(&base_path, Path::new(target), dry_run)?;
+
+        // Save the dropped entry before deleting the branch, so a failing
+        // branch delete cannot leave it pointing at a removed worktree.
+        values.inactive_trees.retain(|path| path != target);
+        if !dry_run {
+            let mut config: Config = serde_json::from_str(&fs::read_to_string(CONFIG_FILE())?)?;
+            config.repo.insert(config.active_repo.clone(), values.clone());
+            fs::write(CONFIG_FILE(), serde_json::to_string_pretty(&config)?)?;
+        }
+
+        if let Some((_, branch)) = branch {
+            backend.delete_branch(&base_path, branch, dry_run)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn restore_worktree(mut values: RepoConfig, branch_name: String, dry_run: bool) -> Result<()> {
+    let backend = backend::backend_for(&values.vcs);
+    let base_path = PathBuf::from(format!("{}/{}", values.base_path, values.base_tree));
+    let worktrees = backend.list_worktrees(&base_path)?;
+
+    // An entry only stays in `inactive_trees` while its worktree still
+    // physically exists (`prune_worktrees` drops it once removed), so the
+    // branch's archived slot can always be resolved through the backend
+    // listing, just like `prune_worktrees` resolves its targets.
+    let worktree_path = worktrees
+        .iter()
+        .find(|(_, branch)| branch == &branch_name)
+        .map(|(path, _)| path.display().to_string())
+        .ok_or_else(|| anyhow!("Worktree for branch {} does not exist", branch_name))?;
+
+    if !values.inactive_trees.contains(&worktree_path) {
+        return Err(anyhow!("Worktree {} is not archived", branch_name));
+    }
+
+    // Reuse the same create-or-switch logic create_worktree relies on for
+    // recycling a freed inactive slot.
+    backend.create_worktree(&base_path, Path::new(&worktree_path), &branch_name, dry_run)?;
+
+    if !dry_run {
+        values.inactive_trees.retain(|path| path != &worktree_path);
+        let mut config: Config = serde_json::from_str(&fs::read_to_string(CONFIG_FILE())?)?;
+        config.repo.insert(config.active_repo.clone(), values);
+        fs::write(CONFIG_FILE(), serde_json::to_string_pretty(&config)?)?;
+    }
+
+    println!("Restored worktree {} at {}", branch_name, worktree_path);
     Ok(())
 }
+
+fn update_main_worktree(values: RepoConfig, dry_run: bool) -> Result<()> {
+    let backend = backend::backend_for(&values.vcs);
+    let base_path = PathBuf::from(format!("{}/{}", values.base_path, values.base_tree));
+    backend.update_base(&base_path, dry_run)
+}
+
+/// Matches `text` against a `*`-wildcard `pattern`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    fn inner(pattern: &[u8], text: &[u8]) -> bool {
+        match pattern.first() {
+            None => text.is_empty(),
+            Some(b'*') => {
+                inner(&pattern[1..], text) || (!text.is_empty() && inner(pattern, &text[1..]))
+            }
+            Some(c) => !text.is_empty() && text[0] == *c && inner(&pattern[1..], &text[1..]),
+        }
+    }
+    inner(pattern.as_bytes(), text.as_bytes())
+}
+
+fn foreach_worktree(
+    values: RepoConfig,
+    command: Vec<String>,
+    branch_filter: Option<String>,
+    dry_run: bool,
+) -> Result<()> {
+    let (program, command_args) = command
+        .split_first()
+        .ok_or_else(|| anyhow!("No command given to run"))?;
+
+    let backend = backend::backend_for(&values.vcs);
+    let base_path = PathBuf::from(format!("{}/{}", values.base_path, values.base_tree));
+    let worktrees = backend.list_worktrees(&base_path)?;
+
+    let mut had_failure = false;
+    for (path, branch) in &worktrees {
+        if values.inactive_trees.contains(&path.display().to_string()) {
+            continue;
+        }
+        if let Some(glob) = &branch_filter {
+            if !glob_match(glob, branch) {
+                continue;
+            }
+        }
+
+        println!("==> {} ({})", path.display(), branch);
+        if dry_run {
+            println!("Would run command {:?} in {}", command, path.display());
+            continue;
+        }
+
+        match Command::new(program)
+            .args(command_args)
+            .current_dir(path)
+            .status()
+        {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                println!("Command failed in {} with {}", path.display(), status);
+                had_failure = true;
+            }
+            Err(err) => {
+                println!("Failed to run command in {}: {}", path.display(), err);
+                had_failure = true;
+            }
+        }
+    }
+
+    if had_failure {
+        return Err(anyhow!("Command failed in one or more worktrees"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod expand_aliases_tests {
+    use super::expand_aliases_with;
+    use std::collections::HashMap;
+
+    fn args(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn no_matching_alias_is_unchanged() {
+        let aliases = HashMap::new();
+        let result = expand_aliases_with(&aliases, args(&["tree-hoprs", "list", "--raw"]));
+        assert_eq!(result, args(&["tree-hoprs", "list", "--raw"]));
+    }
+
+    #[test]
+    fn expands_first_positional_token() {
+        let mut aliases = HashMap::new();
+        aliases.insert("co".to_string(), args(&["create"]));
+        let result = expand_aliases_with(&aliases, args(&["tree-hoprs", "co", "my-branch"]));
+        assert_eq!(result, args(&["tree-hoprs", "create", "my-branch"]));
+    }
+
+    #[test]
+    fn only_expands_once() {
+        let mut aliases = HashMap::new();
+        aliases.insert("co".to_string(), args(&["ci"]));
+        aliases.insert("ci".to_string(), args(&["create"]));
+        let result = expand_aliases_with(&aliases, args(&["tree-hoprs", "co"]));
+        // `ci` is the expansion, not re-scanned for further aliases.
+        assert_eq!(result, args(&["tree-hoprs", "ci"]));
+    }
+
+    #[test]
+    fn extending_a_builtin_with_its_own_name_is_allowed() {
+        let mut aliases = HashMap::new();
+        aliases.insert("list".to_string(), args(&["list", "--raw"]));
+        let result = expand_aliases_with(&aliases, args(&["tree-hoprs", "list"]));
+        assert_eq!(result, args(&["tree-hoprs", "list", "--raw"]));
+    }
+
+    #[test]
+    fn self_referencing_alias_is_left_unexpanded() {
+        let mut aliases = HashMap::new();
+        aliases.insert("co".to_string(), args(&["co"]));
+        let result = expand_aliases_with(&aliases, args(&["tree-hoprs", "co"]));
+        assert_eq!(result, args(&["tree-hoprs", "co"]));
+    }
+
+    #[test]
+    fn value_flag_before_subcommand_is_not_treated_as_subcommand() {
+        let mut aliases = HashMap::new();
+        aliases.insert("co".to_string(), args(&["create"]));
+        let result = expand_aliases_with(
+            &aliases,
+            args(&["tree-hoprs", "-b", "co", "co", "my-branch"]),
+        );
+        // The first "co" is consumed as `-b`'s value, so only the second is expanded.
+        assert_eq!(
+            result,
+            args(&["tree-hoprs", "-b", "co", "create", "my-branch"])
+        );
+    }
+}
+
+#[cfg(test)]
+mod glob_match_tests {
+    use super::glob_match;
+
+    #[test]
+    fn exact_match() {
+        assert!(glob_match("feature/foo", "feature/foo"));
+        assert!(!glob_match("feature/foo", "feature/bar"));
+    }
+
+    #[test]
+    fn trailing_star() {
+        assert!(glob_match("feature/*", "feature/foo"));
+        assert!(glob_match("feature/*", "feature/"));
+        assert!(!glob_match("feature/*", "bugfix/foo"));
+    }
+
+    #[test]
+    fn leading_star() {
+        assert!(glob_match("*-1234", "feature-1234"));
+        assert!(!glob_match("*-1234", "feature-1235"));
+    }
+
+    #[test]
+    fn star_in_middle() {
+        assert!(glob_match("feature/*/done", "feature/foo/done"));
+        assert!(glob_match("feature/*/done", "feature//done"));
+        assert!(!glob_match("feature/*/done", "feature/foo/pending"));
+    }
+
+    #[test]
+    fn bare_star_matches_anything() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything"));
+    }
+
+    #[test]
+    fn empty_pattern_only_matches_empty_text() {
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "anything"));
+    }
+}